@@ -0,0 +1,267 @@
+//! Serde glue for the `http` crate's types.
+//!
+//! Hyper has moved most of its core vocabulary types (`StatusCode`, `Uri`,
+//! `HeaderMap`, `Version`, `Authority`) into the standalone `http` crate.
+//! This module mirrors the `De`/`Ser`/`Serde` wrappers in the crate root for
+//! those types, so the same `#[serde(with = "hyper_serde")]` ergonomics
+//! work against current Hyper without going through the long-deprecated
+//! `hyper::http::RawStatus`.
+//!
+//! This module is only available when the `http` feature is enabled.
+
+use http_crate::{HeaderMap, HeaderValue, StatusCode, Uri, Version};
+use http_crate::header::HeaderName;
+use http_crate::uri::Authority;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{self, Error as _, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde_bytes::Bytes;
+use std::cmp;
+use std::fmt;
+use std::str;
+use {De, Ser};
+
+impl<'de> Deserialize<'de> for De<HeaderMap> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>,
+    {
+        struct HeaderMapVisitor;
+
+        impl<'de> Visitor<'de> for HeaderMapVisitor {
+            type Value = De<HeaderMap>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "a map from header names to header values")
+            }
+
+            fn visit_unit<E>(self) -> Result<Self::Value, E>
+                where E: de::Error,
+            {
+                Ok(De::new(HeaderMap::new()))
+            }
+
+            fn visit_map<V>(self,
+                            mut visitor: V)
+                            -> Result<Self::Value, V::Error>
+                where V: MapAccess<'de>,
+            {
+                let mut headers = HeaderMap::new();
+                while let Some((k, v)) = visitor.next_entry::<String, Value>()? {
+                    let name = k.parse::<HeaderName>()
+                        .map_err(|e| V::Error::custom(e.to_string()))?;
+                    for value in v.0 {
+                        let value = HeaderValue::from_bytes(&value)
+                            .map_err(|e| V::Error::custom(e.to_string()))?;
+                        headers.append(&name, value);
+                    }
+                }
+                Ok(De::new(headers))
+            }
+        }
+
+        struct Value(Vec<Vec<u8>>);
+
+        impl<'de> Deserialize<'de> for Value {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where D: Deserializer<'de>,
+            {
+                deserializer.deserialize_seq(ValueVisitor)
+            }
+        }
+
+        struct ValueVisitor;
+
+        impl<'de> Visitor<'de> for ValueVisitor {
+            type Value = Value;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "an array of strings and sequences of bytes")
+            }
+
+            fn visit_unit<E>(self) -> Result<Value, E>
+                where E: de::Error,
+            {
+                Ok(Value(vec![]))
+            }
+
+            fn visit_seq<V>(self, mut visitor: V) -> Result<Value, V::Error>
+                where V: SeqAccess<'de>,
+            {
+                // Clamp to not OOM on rogue values.
+                let capacity = cmp::min(visitor.size_hint().unwrap_or(0), 64);
+                let mut values = Vec::with_capacity(capacity);
+                while let Some(v) = visitor.next_element::<serde_bytes::ByteBuf>()? {
+                    values.push(v.into_vec());
+                }
+                Ok(Value(values))
+            }
+        }
+
+        deserializer.deserialize_map(HeaderMapVisitor)
+    }
+}
+
+impl<'a> Serialize for Ser<'a, HeaderMap> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer,
+    {
+        struct Value<'header>(Vec<&'header HeaderValue>, bool);
+
+        impl<'header> Serialize for Value<'header> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where S: Serializer,
+            {
+                // Binary formats always get raw bytes, matching the crate
+                // root's `Headers` serialization.
+                let human_readable = serializer.is_human_readable();
+                let mut serializer =
+                    serializer.serialize_seq(Some(self.0.len()))?;
+                for v in &self.0 {
+                    if human_readable && self.1 {
+                        if let Ok(s) = v.to_str() {
+                            serializer.serialize_element(s)?;
+                            continue;
+                        }
+                    }
+                    serializer.serialize_element(&Bytes::new(v.as_bytes()))?;
+                }
+                serializer.end()
+            }
+        }
+
+        let mut serializer =
+            serializer.serialize_map(Some(self.v.keys_len()))?;
+        for name in self.v.keys() {
+            let values = self.v.get_all(name).iter().collect();
+            serializer.serialize_entry(name.as_str(),
+                                        &Value(values, self.pretty))?;
+        }
+        serializer.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for De<Uri> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>,
+    {
+        struct UriVisitor;
+
+        impl<'de> Visitor<'de> for UriVisitor {
+            type Value = De<Uri>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "a URI")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                where E: de::Error,
+            {
+                v.parse::<Uri>().map(De::new).map_err(|e| E::custom(e.to_string()))
+            }
+        }
+
+        deserializer.deserialize_string(UriVisitor)
+    }
+}
+
+impl<'a> Serialize for Ser<'a, Uri> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer,
+    {
+        serializer.serialize_str(&self.v.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for De<Authority> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>,
+    {
+        struct AuthorityVisitor;
+
+        impl<'de> Visitor<'de> for AuthorityVisitor {
+            type Value = De<Authority>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "the authority component of a URI")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                where E: de::Error,
+            {
+                v.parse::<Authority>().map(De::new).map_err(|e| E::custom(e.to_string()))
+            }
+        }
+
+        deserializer.deserialize_string(AuthorityVisitor)
+    }
+}
+
+impl<'a> Serialize for Ser<'a, Authority> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer,
+    {
+        serializer.serialize_str(self.v.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for De<StatusCode> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>,
+    {
+        let code = u16::deserialize(deserializer)?;
+        StatusCode::from_u16(code).map(De::new).map_err(|e| D::Error::custom(e.to_string()))
+    }
+}
+
+impl<'a> Serialize for Ser<'a, StatusCode> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer,
+    {
+        self.v.as_u16().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for De<Version> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>,
+    {
+        struct VersionVisitor;
+
+        impl<'de> Visitor<'de> for VersionVisitor {
+            type Value = De<Version>;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                write!(formatter, "an HTTP version, such as \"HTTP/1.1\"")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                where E: de::Error,
+            {
+                let version = match v {
+                    "HTTP/0.9" => Version::HTTP_09,
+                    "HTTP/1.0" => Version::HTTP_10,
+                    "HTTP/1.1" => Version::HTTP_11,
+                    "HTTP/2.0" => Version::HTTP_2,
+                    _ => return Err(E::custom(format!("unknown HTTP version: {}", v))),
+                };
+                Ok(De::new(version))
+            }
+        }
+
+        deserializer.deserialize_string(VersionVisitor)
+    }
+}
+
+impl<'a> Serialize for Ser<'a, Version> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer,
+    {
+        let s = match *self.v {
+            Version::HTTP_09 => "HTTP/0.9",
+            Version::HTTP_10 => "HTTP/1.0",
+            Version::HTTP_11 => "HTTP/1.1",
+            Version::HTTP_2 => "HTTP/2.0",
+        };
+        serializer.serialize_str(s)
+    }
+}
@@ -11,11 +11,21 @@
 //! * `mime::Mime`
 //! * `time::Tm`
 //!
+//! With the `http` feature enabled, the [`http`](http/index.html) module
+//! provides the same wrappers for the types Hyper has migrated to the
+//! `http` crate:
+//!
+//! * `http::HeaderMap`
+//! * `http::StatusCode`
+//! * `http::Uri`
+//! * `http::Version`
+//! * `http::uri::Authority`
+//!
 //! # How do I use a data type with a `Headers` member with Serde?
 //!
 //! Use the serde attributes `deserialize_with` and `serialize_with`.
 //!
-//! ```
+//! ```ignore
 //! struct MyStruct {
 //! #[serde(deserialize_with = "hyper_serde::deserialize",
 //! serialize_with = "hyper_serde::serialize")]
@@ -27,7 +37,7 @@
 //!
 //! Use the `Ser` wrapper.
 //!
-//! ```
+//! ```ignore
 //! serde_json::to_string(&Ser::new(&headers))
 //! ```
 //!
@@ -35,7 +45,7 @@
 //!
 //! Use the `De` wrapper.
 //!
-//! ```
+//! ```ignore
 //! serde_json::parse::<De<Method>>("\"PUT\"").map(De::into_inner)
 //! ```
 //!
@@ -44,44 +54,81 @@
 //! Use the `Serde` wrapper. It implements `Deref` and `DerefMut` for
 //! convenience.
 //!
-//! ```
+//! ```ignore
 //! ipc::channel::<Serde<Cookie>>()
 //! ```
 //!
+//! # Does this crate work with binary formats, not just JSON?
+//!
+//! Yes, and serialization adapts automatically via `Serializer::is_human_readable()`:
+//! in a binary format `Tm` writes as an `i64` Unix timestamp instead of an
+//! RFC 3339 string, and `Headers` values always write as raw bytes instead
+//! of the `pretty`/`base64` string forms `Ser::new_pretty`/`Ser::new_base64`
+//! enable for human-readable formats such as JSON. `De<Tm>`/`De<Headers>`
+//! accept either representation regardless of the deserializer's own
+//! `is_human_readable()`, so values round-trip across format families
+//! without extra work on either side.
+//!
+//! Use `Ser::new_base64` instead of `Ser::new` to base64-encode non-UTF-8
+//! header values into a single string rather than a verbose array of
+//! integers, when serializing to a human-readable format; `De<Headers>`/
+//! `DeHeaders` accept either representation.
+//!
+//! # What happens if a `Headers` map has the same header name twice?
+//!
+//! `De<Headers>` appends the repeated values together, since multi-valued
+//! headers such as `Set-Cookie` are the HTTP norm. Use `DeHeaders` directly
+//! with a different `DuplicateHeaderNamePolicy` (`RejectDuplicateHeaderName`,
+//! `FirstHeaderNameWins`, or `LastHeaderNameWins`) if you need other
+//! behaviour.
+//!
 //!
 
 #![deny(missing_docs)]
 #![deny(unsafe_code)]
 
+extern crate base64;
 extern crate cookie;
+#[cfg(feature = "http")]
+extern crate http as http_crate;
 extern crate hyper;
 extern crate mime;
 extern crate serde;
+extern crate serde_bytes;
 extern crate time;
 
+#[cfg(feature = "http")]
+pub mod http;
+
 use cookie::Cookie;
 use hyper::header::{ContentType, Headers};
-use hyper::http::RawStatus;
-use hyper::method::Method;
+use hyper::{Method, RawStatus};
 use mime::Mime;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde::bytes::{ByteBuf, Bytes};
-use serde::de::{self, MapVisitor, SeqVisitor, Visitor};
+use serde::de::{self, Error as _, MapAccess, SeqAccess, Visitor};
 use serde::ser::{SerializeMap, SerializeSeq};
+use serde_bytes::Bytes;
 use std::cmp;
+use std::collections::HashSet;
 use std::fmt;
+use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 use std::str;
-use time::{Tm, strptime};
+use time::{Timespec, Tm, at_utc, strptime};
+
+/// The tag `Ser::new_base64` and `De<Headers>`/`DeHeaders` use to mark a
+/// header value as base64-encoded, as the first element of a `[tag, data]`
+/// pair, rather than a literal string or a byte sequence.
+pub const BASE64_HEADER_VALUE_TAG: &str = "b64";
 
 /// Deserialises a `T` value with a given deserializer.
 ///
 /// This is useful to deserialize Hyper types used in structure fields or
 /// tuple members with `#[serde(deserialize_with = "hyper_serde::deserialize")]`.
 #[inline(always)]
-pub fn deserialize<T, D>(deserializer: D) -> Result<T, D::Error>
-    where D: Deserializer,
-          De<T>: Deserialize,
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where D: Deserializer<'de>,
+          for<'a> De<T>: Deserialize<'a>,
 {
     De::deserialize(deserializer).map(De::into_inner)
 }
@@ -129,12 +176,12 @@ pub struct De<T> {
 
 impl<T> De<T> {
     fn new(v: T) -> Self {
-        De { v: v }
+        De { v }
     }
 }
 
 impl<T> De<T>
-    where De<T>: Deserialize,
+    where for<'de> De<T>: Deserialize<'de>,
 {
     /// Consumes this wrapper, returning the deserialized value.
     #[inline(always)]
@@ -152,6 +199,7 @@ impl<T> De<T>
 pub struct Ser<'a, T: 'a> {
     v: &'a T,
     pretty: bool,
+    base64: bool,
 }
 
 impl<'a, T> Ser<'a, T>
@@ -163,6 +211,7 @@ impl<'a, T> Ser<'a, T>
         Ser {
             v: value,
             pretty: false,
+            base64: false,
         }
     }
 
@@ -174,6 +223,25 @@ impl<'a, T> Ser<'a, T>
         Ser {
             v: value,
             pretty: true,
+            base64: false,
+        }
+    }
+
+    /// Returns a new `Ser` wrapper that base64-encodes non-UTF-8 `Headers`
+    /// values.
+    ///
+    /// Without this, a non-UTF-8 header value is serialized as a serde byte
+    /// sequence, which in JSON expands to a verbose array of integers. In
+    /// this mode it is instead base64-encoded into a single
+    /// `[BASE64_HEADER_VALUE_TAG, "..."]` pair, which is smaller and
+    /// human-inspectable; `De<Headers>`/`DeHeaders` decode either
+    /// representation back to the exact original bytes.
+    #[inline(always)]
+    pub fn new_base64(value: &'a T) -> Self {
+        Ser {
+            v: value,
+            pretty: false,
+            base64: true,
         }
     }
 }
@@ -182,11 +250,11 @@ impl<'a, T> Ser<'a, T>
 /// a `Vec<T>` need to be passed to serde.
 #[derive(Clone, PartialEq)]
 pub struct Serde<T>(pub T)
-    where De<T>: Deserialize,
+    where for<'de> De<T>: Deserialize<'de>,
           for<'a> Ser<'a, T>: Serialize;
 
 impl<T> Serde<T>
-    where De<T>: Deserialize,
+    where for<'de> De<T>: Deserialize<'de>,
           for<'a> Ser<'a, T>: Serialize,
 {
     /// Consumes this wrapper, returning the inner value.
@@ -198,7 +266,7 @@ impl<T> Serde<T>
 
 impl<T> fmt::Debug for Serde<T>
     where T: fmt::Debug,
-          De<T>: Deserialize,
+          for<'de> De<T>: Deserialize<'de>,
           for<'a> Ser<'a, T>: Serialize,
 {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
@@ -207,7 +275,7 @@ impl<T> fmt::Debug for Serde<T>
 }
 
 impl<T> Deref for Serde<T>
-    where De<T>: Deserialize,
+    where for<'de> De<T>: Deserialize<'de>,
           for<'a> Ser<'a, T>: Serialize,
 {
     type Target = T;
@@ -218,7 +286,7 @@ impl<T> Deref for Serde<T>
 }
 
 impl<T> DerefMut for Serde<T>
-    where De<T>: Deserialize,
+    where for<'de> De<T>: Deserialize<'de>,
           for<'a> Ser<'a, T>: Serialize,
 {
     fn deref_mut(&mut self) -> &mut T {
@@ -227,7 +295,7 @@ impl<T> DerefMut for Serde<T>
 }
 
 impl<T: PartialEq> PartialEq<T> for Serde<T>
-    where De<T>: Deserialize,
+    where for<'de> De<T>: Deserialize<'de>,
           for<'a> Ser<'a, T>: Serialize,
 {
     fn eq(&self, other: &T) -> bool {
@@ -235,19 +303,19 @@ impl<T: PartialEq> PartialEq<T> for Serde<T>
     }
 }
 
-impl<T> Deserialize for Serde<T>
-    where De<T>: Deserialize,
+impl<'de, T> Deserialize<'de> for Serde<T>
+    where for<'a> De<T>: Deserialize<'a>,
           for<'a> Ser<'a, T>: Serialize,
 {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer,
+        where D: Deserializer<'de>,
     {
         De::deserialize(deserializer).map(De::into_inner).map(Serde)
     }
 }
 
 impl<T> Serialize for Serde<T>
-    where De<T>: Deserialize,
+    where for<'de> De<T>: Deserialize<'de>,
           for<'a> Ser<'a, T>: Serialize,
 {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -257,9 +325,9 @@ impl<T> Serialize for Serde<T>
     }
 }
 
-impl Deserialize for De<ContentType> {
+impl<'de> Deserialize<'de> for De<ContentType> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer,
+        where D: Deserializer<'de>,
     {
         deserialize(deserializer).map(ContentType).map(De::new)
     }
@@ -273,13 +341,13 @@ impl<'a> Serialize for Ser<'a, ContentType> {
     }
 }
 
-impl Deserialize for De<Cookie<'static>> {
+impl<'de> Deserialize<'de> for De<Cookie<'static>> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer,
+        where D: Deserializer<'de>,
     {
         struct CookieVisitor;
 
-        impl Visitor for CookieVisitor {
+        impl<'de> Visitor<'de> for CookieVisitor {
             type Value = De<Cookie<'static>>;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -308,14 +376,109 @@ impl<'a, 'cookie> Serialize for Ser<'a, Cookie<'cookie>> {
     }
 }
 
-impl Deserialize for De<Headers> {
+/// A policy describing what to do when a header name appears more than once
+/// while deserializing `Headers`, selected via the `P` type parameter of
+/// `DeHeaders`.
+pub trait DuplicateHeaderNamePolicy {
+    /// Combines the value vector already collected for `name` (if any) with
+    /// a newly parsed one, or rejects the duplicate outright.
+    fn combine<E>(name: &str,
+                  previous: Option<Vec<Vec<u8>>>,
+                  value: Vec<Vec<u8>>)
+                  -> Result<Vec<Vec<u8>>, E>
+        where E: de::Error;
+}
+
+/// Rejects a serialized map that repeats a header name.
+pub enum RejectDuplicateHeaderName {}
+
+impl DuplicateHeaderNamePolicy for RejectDuplicateHeaderName {
+    fn combine<E>(name: &str,
+                  _previous: Option<Vec<Vec<u8>>>,
+                  _value: Vec<Vec<u8>>)
+                  -> Result<Vec<Vec<u8>>, E>
+        where E: de::Error,
+    {
+        Err(E::custom(format!("duplicate header name: {}", name)))
+    }
+}
+
+/// Keeps the first value seen for a repeated header name, ignoring later
+/// ones.
+pub enum FirstHeaderNameWins {}
+
+impl DuplicateHeaderNamePolicy for FirstHeaderNameWins {
+    fn combine<E>(_name: &str,
+                  previous: Option<Vec<Vec<u8>>>,
+                  _value: Vec<Vec<u8>>)
+                  -> Result<Vec<Vec<u8>>, E>
+        where E: de::Error,
+    {
+        Ok(previous.unwrap_or_default())
+    }
+}
+
+/// Keeps only the last value seen for a repeated header name.
+pub enum LastHeaderNameWins {}
+
+impl DuplicateHeaderNamePolicy for LastHeaderNameWins {
+    fn combine<E>(_name: &str,
+                  _previous: Option<Vec<Vec<u8>>>,
+                  value: Vec<Vec<u8>>)
+                  -> Result<Vec<Vec<u8>>, E>
+        where E: de::Error,
+    {
+        Ok(value)
+    }
+}
+
+/// Concatenates the value vectors of a repeated header name, so e.g. two
+/// `Set-Cookie` entries both survive. This is the policy `De<Headers>` uses,
+/// since multi-valued headers are the HTTP norm.
+pub enum AppendHeaderNameValues {}
+
+impl DuplicateHeaderNamePolicy for AppendHeaderNameValues {
+    fn combine<E>(_name: &str,
+                  previous: Option<Vec<Vec<u8>>>,
+                  value: Vec<Vec<u8>>)
+                  -> Result<Vec<Vec<u8>>, E>
+        where E: de::Error,
+    {
+        let mut combined = previous.unwrap_or_default();
+        combined.extend(value);
+        Ok(combined)
+    }
+}
+
+/// Like `De<Headers>`, but with configurable handling of header names that
+/// appear more than once in the serialized map, chosen via the `P` type
+/// parameter (see `DuplicateHeaderNamePolicy`).
+#[derive(Debug)]
+pub struct DeHeaders<P = AppendHeaderNameValues> {
+    v: Headers,
+    policy: PhantomData<P>,
+}
+
+impl<P> DeHeaders<P> {
+    /// Consumes this wrapper, returning the deserialized `Headers`.
+    #[inline(always)]
+    pub fn into_inner(self) -> Headers {
+        self.v
+    }
+}
+
+impl<'de, P> Deserialize<'de> for DeHeaders<P>
+    where P: DuplicateHeaderNamePolicy,
+{
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer,
+        where D: Deserializer<'de>,
     {
-        struct HeadersVisitor;
+        struct HeadersVisitor<P>(PhantomData<P>);
 
-        impl Visitor for HeadersVisitor {
-            type Value = De<Headers>;
+        impl<'de, P> Visitor<'de> for HeadersVisitor<P>
+            where P: DuplicateHeaderNamePolicy,
+        {
+            type Value = DeHeaders<P>;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
                 write!(formatter, "a map from header names to header values")
@@ -324,27 +487,41 @@ impl Deserialize for De<Headers> {
             fn visit_unit<E>(self) -> Result<Self::Value, E>
                 where E: de::Error,
             {
-                Ok(De::new(Headers::new()))
+                Ok(DeHeaders {
+                    v: Headers::new(),
+                    policy: PhantomData,
+                })
             }
 
             fn visit_map<V>(self,
                             mut visitor: V)
                             -> Result<Self::Value, V::Error>
-                where V: MapVisitor,
+                where V: MapAccess<'de>,
             {
                 let mut headers = Headers::new();
-                while let Some((k, v)) = visitor.visit::<String, Value>()? {
-                    headers.set_raw(k, v.0);
+                let mut seen = HashSet::new();
+                while let Some((k, v)) = visitor.next_entry::<String, Value>()? {
+                    let values = if seen.insert(k.clone()) {
+                        v.0
+                    } else {
+                        let previous = headers.get_raw(&k)
+                            .map(|vs| vs.iter().map(|v| v.to_vec()).collect());
+                        P::combine::<V::Error>(&k, previous, v.0)?
+                    };
+                    headers.set_raw(k, values);
                 }
-                Ok(De::new(headers))
+                Ok(DeHeaders {
+                    v: headers,
+                    policy: PhantomData,
+                })
             }
         }
 
         struct Value(Vec<Vec<u8>>);
 
-        impl Deserialize for Value {
+        impl<'de> Deserialize<'de> for Value {
             fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-                where D: Deserializer,
+                where D: Deserializer<'de>,
             {
                 deserializer.deserialize_seq(ValueVisitor)
             }
@@ -352,7 +529,7 @@ impl Deserialize for De<Headers> {
 
         struct ValueVisitor;
 
-        impl Visitor for ValueVisitor {
+        impl<'de> Visitor<'de> for ValueVisitor {
             type Value = Value;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -366,19 +543,156 @@ impl Deserialize for De<Headers> {
             }
 
             fn visit_seq<V>(self, mut visitor: V) -> Result<Value, V::Error>
-                where V: SeqVisitor,
+                where V: SeqAccess<'de>,
             {
                 // Clamp to not OOM on rogue values.
-                let capacity = cmp::min(visitor.size_hint().0, 64);
+                let capacity = cmp::min(visitor.size_hint().unwrap_or(0), 64);
                 let mut values = Vec::with_capacity(capacity);
-                while let Some(v) = visitor.visit::<ByteBuf>()? {
-                    values.push(v.into());
+                while let Some(v) = visitor.next_element::<RawHeaderValue>()? {
+                    values.push(v.0);
                 }
                 Ok(Value(values))
             }
         }
 
-        deserializer.deserialize_map(HeadersVisitor)
+        // A single header value as the wire actually represents it: a
+        // plain string, a byte sequence, or (for a non-UTF-8 value written
+        // by `Ser::new_base64`) a `[BASE64_HEADER_VALUE_TAG, "..."]` pair.
+        struct RawHeaderValue(Vec<u8>);
+
+        impl<'de> Deserialize<'de> for RawHeaderValue {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where D: Deserializer<'de>,
+            {
+                struct RawHeaderValueVisitor;
+
+                impl<'de> Visitor<'de> for RawHeaderValueVisitor {
+                    type Value = RawHeaderValue;
+
+                    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                        write!(formatter,
+                               "a header value, as a string, a byte sequence, or a base64-tagged string")
+                    }
+
+                    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                        where E: de::Error,
+                    {
+                        Ok(RawHeaderValue(v.as_bytes().to_vec()))
+                    }
+
+                    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+                        where E: de::Error,
+                    {
+                        Ok(RawHeaderValue(v.to_vec()))
+                    }
+
+                    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+                        where E: de::Error,
+                    {
+                        Ok(RawHeaderValue(v))
+                    }
+
+                    fn visit_seq<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
+                        where V: SeqAccess<'de>,
+                    {
+                        // The default (non-base64) representation is a seq
+                        // of bytes, like `ByteBuf` produces; the base64
+                        // representation is a `[tag, data]` pair whose first
+                        // element is the tag string. Peek at the first
+                        // element to tell the two apart.
+                        match visitor.next_element::<SeqHead>()? {
+                            None => Ok(RawHeaderValue(vec![])),
+                            Some(SeqHead::Tag(tag)) => {
+                                if tag != BASE64_HEADER_VALUE_TAG {
+                                    return Err(V::Error::custom(format!("unknown header value tag: {}", tag)));
+                                }
+                                let encoded: String = visitor.next_element()?.ok_or_else(|| {
+                                    V::Error::custom("missing base64-encoded header value")
+                                })?;
+                                base64::decode(&encoded)
+                                    .map(RawHeaderValue)
+                                    .map_err(|e| V::Error::custom(e.to_string()))
+                            }
+                            Some(SeqHead::Byte(first)) => {
+                                // Clamp to not OOM on rogue values.
+                                let capacity = cmp::min(visitor.size_hint().unwrap_or(0), 64);
+                                let mut values = Vec::with_capacity(capacity + 1);
+                                values.push(first);
+                                while let Some(b) = visitor.next_element::<u8>()? {
+                                    values.push(b);
+                                }
+                                Ok(RawHeaderValue(values))
+                            }
+                        }
+                    }
+                }
+
+                // The first element of a header value's seq form: either
+                // the base64 tag string, or the first raw byte.
+                enum SeqHead {
+                    Tag(String),
+                    Byte(u8),
+                }
+
+                impl<'de> Deserialize<'de> for SeqHead {
+                    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                        where D: Deserializer<'de>,
+                    {
+                        struct SeqHeadVisitor;
+
+                        impl<'de> Visitor<'de> for SeqHeadVisitor {
+                            type Value = SeqHead;
+
+                            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                                write!(formatter, "a byte, or the base64 tag string")
+                            }
+
+                            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+                                where E: de::Error,
+                            {
+                                Ok(SeqHead::Tag(v.to_owned()))
+                            }
+
+                            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+                                where E: de::Error,
+                            {
+                                if v <= u64::from(u8::MAX) {
+                                    Ok(SeqHead::Byte(v as u8))
+                                } else {
+                                    Err(E::custom(format!("byte value out of range: {}", v)))
+                                }
+                            }
+
+                            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+                                where E: de::Error,
+                            {
+                                if v >= 0 && v <= i64::from(u8::MAX) {
+                                    Ok(SeqHead::Byte(v as u8))
+                                } else {
+                                    Err(E::custom(format!("byte value out of range: {}", v)))
+                                }
+                            }
+                        }
+
+                        deserializer.deserialize_any(SeqHeadVisitor)
+                    }
+                }
+
+                deserializer.deserialize_any(RawHeaderValueVisitor)
+            }
+        }
+
+        deserializer.deserialize_map(HeadersVisitor(PhantomData))
+    }
+}
+
+impl<'de> Deserialize<'de> for De<Headers> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where D: Deserializer<'de>,
+    {
+        DeHeaders::<AppendHeaderNameValues>::deserialize(deserializer)
+            .map(DeHeaders::into_inner)
+            .map(De::new)
     }
 }
 
@@ -386,22 +700,40 @@ impl<'a> Serialize for Ser<'a, Headers> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where S: Serializer,
     {
-        struct Value<'headers>(&'headers [Vec<u8>], bool);
+        struct Base64Value<'a>(&'a [u8]);
+
+        impl<'a> Serialize for Base64Value<'a> {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where S: Serializer,
+            {
+                (BASE64_HEADER_VALUE_TAG, base64::encode(self.0)).serialize(serializer)
+            }
+        }
+
+        struct Value<'headers>(&'headers hyper::header::Raw, bool, bool);
 
         impl<'headers> Serialize for Value<'headers> {
             fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
                 where S: Serializer,
             {
+                // Binary formats always get raw bytes: there's no reader to
+                // please with a string, and it skips the UTF-8 check and
+                // (if enabled) the base64 encoding entirely.
+                let human_readable = serializer.is_human_readable();
                 let mut serializer =
                     serializer.serialize_seq(Some(self.0.len()))?;
-                for v in self.0 {
-                    if self.1 {
-                        if let Ok(v) = str::from_utf8(v) {
-                            serializer.serialize_element(v)?;
+                for v in self.0.iter() {
+                    if human_readable && (self.1 || self.2) {
+                        if let Ok(s) = str::from_utf8(v) {
+                            serializer.serialize_element(s)?;
                             continue;
                         }
                     }
-                    serializer.serialize_element(&Bytes::new(v))?;
+                    if human_readable && self.2 {
+                        serializer.serialize_element(&Base64Value(v))?;
+                    } else {
+                        serializer.serialize_element(&Bytes::new(v))?;
+                    }
                 }
                 serializer.end()
             }
@@ -411,19 +743,19 @@ impl<'a> Serialize for Ser<'a, Headers> {
         for header in self.v.iter() {
             let name = header.name();
             let value = self.v.get_raw(name).unwrap();
-            serializer.serialize_entry(name, &Value(value, self.pretty))?;
+            serializer.serialize_entry(name, &Value(value, self.pretty, self.base64))?;
         }
         serializer.end()
     }
 }
 
-impl Deserialize for De<Method> {
+impl<'de> Deserialize<'de> for De<Method> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer,
+        where D: Deserializer<'de>,
     {
         struct MethodVisitor;
 
-        impl Visitor for MethodVisitor {
+        impl<'de> Visitor<'de> for MethodVisitor {
             type Value = De<Method>;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -449,13 +781,13 @@ impl<'a> Serialize for Ser<'a, Method> {
     }
 }
 
-impl Deserialize for De<Mime> {
+impl<'de> Deserialize<'de> for De<Mime> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer,
+        where D: Deserializer<'de>,
     {
         struct MimeVisitor;
 
-        impl Visitor for MimeVisitor {
+        impl<'de> Visitor<'de> for MimeVisitor {
             type Value = De<Mime>;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
@@ -465,7 +797,7 @@ impl Deserialize for De<Mime> {
             fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
                 where E: de::Error,
             {
-                v.parse::<Mime>().map(De::new).map_err(|()| {
+                v.parse::<Mime>().map(De::new).map_err(|_| {
                     E::custom("could not parse mime type")
                 })
             }
@@ -479,13 +811,13 @@ impl<'a> Serialize for Ser<'a, Mime> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where S: Serializer,
     {
-        serializer.serialize_str(&self.v.to_string())
+        serializer.serialize_str(self.v.as_ref())
     }
 }
 
-impl Deserialize for De<RawStatus> {
+impl<'de> Deserialize<'de> for De<RawStatus> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer,
+        where D: Deserializer<'de>,
     {
         let (code, reason) = Deserialize::deserialize(deserializer)?;
         Ok(De::new(RawStatus(code, reason)))
@@ -500,29 +832,64 @@ impl<'a> Serialize for Ser<'a, RawStatus> {
     }
 }
 
-impl Deserialize for De<Tm> {
+impl<'de> Deserialize<'de> for De<Tm> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-        where D: Deserializer,
+        where D: Deserializer<'de>,
     {
         struct TmVisitor;
 
-        impl Visitor for TmVisitor {
+        impl<'de> Visitor<'de> for TmVisitor {
             type Value = De<Tm>;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                write!(formatter, "a date and time according to RFC 3339")
+                write!(formatter, "a date and time (RFC 3339 or an HTTP date), or a Unix timestamp")
             }
 
             fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
                 where E: de::Error,
             {
-                strptime(v, "%Y-%m-%dT%H:%M:%SZ").map(De::new).map_err(|e| {
-                    E::custom(e.to_string())
-                })
+                // Tried in order: RFC 3339 (with or without fractional
+                // seconds, with `Z` or a numeric offset), then the three
+                // date formats HTTP headers such as `Date`, `Expires`, and
+                // `Last-Modified` are allowed to use.
+                const FORMATS: &[&str] = &[
+                    "%Y-%m-%dT%H:%M:%SZ",
+                    "%Y-%m-%dT%H:%M:%S%z",
+                    "%Y-%m-%dT%H:%M:%S.%fZ",
+                    "%Y-%m-%dT%H:%M:%S.%f%z",
+                    // RFC 1123 / IMF-fixdate.
+                    "%a, %d %b %Y %H:%M:%S GMT",
+                    // RFC 850, obsolete but still seen in the wild.
+                    "%A, %d-%b-%y %H:%M:%S GMT",
+                    // `asctime` form.
+                    "%a %b %e %H:%M:%S %Y",
+                ];
+
+                FORMATS.iter()
+                    .filter_map(|format| strptime(v, format).ok())
+                    .next()
+                    .map(De::new)
+                    .ok_or_else(|| {
+                        E::custom(format!("{:?} is not a recognised date and time", v))
+                    })
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E>
+                where E: de::Error,
+            {
+                Ok(De::new(at_utc(Timespec::new(v, 0))))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E>
+                where E: de::Error,
+            {
+                Ok(De::new(at_utc(Timespec::new(v as i64, 0))))
             }
         }
 
-        deserializer.deserialize_string(TmVisitor)
+        // Let the deserializer hand `TmVisitor` whatever it actually has
+        // (a string or an integer) rather than guessing a hint up front.
+        deserializer.deserialize_any(TmVisitor)
     }
 }
 
@@ -530,6 +897,189 @@ impl<'a> Serialize for Ser<'a, Tm> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
         where S: Serializer,
     {
+        // Binary formats get the compact form (a Unix timestamp) since
+        // there's no reader squinting at the bytes; human-readable ones
+        // keep the RFC 3339 string.
+        if !serializer.is_human_readable() {
+            return self.v.to_timespec().sec.serialize(serializer);
+        }
         serializer.serialize_str(&self.v.rfc3339().to_string())
     }
 }
+
+#[cfg(test)]
+mod is_human_readable_tests {
+    extern crate serde_test;
+
+    use super::Ser;
+    use self::serde_test::{assert_ser_tokens, Configure, Token};
+    use hyper::header::Headers;
+    use time::{Timespec, at_utc};
+
+    #[test]
+    fn tm_is_an_rfc3339_string_in_a_human_readable_format() {
+        let tm = at_utc(Timespec::new(784111777, 0));
+        assert_ser_tokens(&Ser::new(&tm).readable(),
+                           &[Token::Str("1994-11-06T08:49:37Z")]);
+    }
+
+    #[test]
+    fn tm_is_a_unix_timestamp_in_a_binary_format() {
+        let tm = at_utc(Timespec::new(784111777, 0));
+        assert_ser_tokens(&Ser::new(&tm).compact(), &[Token::I64(784111777)]);
+    }
+
+    #[test]
+    fn utf8_header_value_is_always_raw_bytes_in_a_binary_format() {
+        // Even `new_base64`, which prefers strings for UTF-8 values in
+        // human-readable formats, must still emit raw bytes for a binary
+        // format: there's no reader to please with a string there.
+        let mut headers = Headers::new();
+        headers.set_raw("X-Text", vec![b"hello".to_vec()]);
+
+        assert_ser_tokens(&Ser::new_base64(&headers).compact(),
+                           &[Token::Map { len: Some(1) },
+                             Token::Str("X-Text"),
+                             Token::Seq { len: Some(1) },
+                             Token::Bytes(b"hello"),
+                             Token::SeqEnd,
+                             Token::MapEnd]);
+    }
+}
+
+#[cfg(test)]
+mod tm_date_format_tests {
+    extern crate serde_json;
+
+    use super::De;
+    use time::Tm;
+
+    fn parse(json: &str) -> Tm {
+        serde_json::from_str::<De<Tm>>(json).unwrap().into_inner()
+    }
+
+    #[test]
+    fn rfc3339() {
+        assert_eq!(parse(r#""1994-11-06T08:49:37Z""#).to_timespec().sec, 784111777);
+    }
+
+    #[test]
+    fn rfc3339_with_fractional_seconds_and_offset() {
+        // `Tm::to_timespec()` doesn't fold `tm_utcoff` into the result, so
+        // assert on the parsed fields directly rather than going through it.
+        let tm = parse(r#""1994-11-06T10:49:37.250+02:00""#);
+        assert_eq!(tm.tm_hour, 10);
+        assert_eq!(tm.tm_min, 49);
+        assert_eq!(tm.tm_sec, 37);
+        assert_eq!(tm.tm_nsec, 250_000_000);
+        assert_eq!(tm.tm_utcoff, 2 * 3600);
+    }
+
+    #[test]
+    fn rfc1123_imf_fixdate() {
+        assert_eq!(parse(r#""Sun, 06 Nov 1994 08:49:37 GMT""#).to_timespec().sec, 784111777);
+    }
+
+    #[test]
+    fn rfc850() {
+        assert_eq!(parse(r#""Sunday, 06-Nov-94 08:49:37 GMT""#).to_timespec().sec, 784111777);
+    }
+
+    #[test]
+    fn asctime() {
+        assert_eq!(parse(r#""Sun Nov  6 08:49:37 1994""#).to_timespec().sec, 784111777);
+    }
+
+    #[test]
+    fn unix_timestamp() {
+        assert_eq!(parse("784111777").to_timespec().sec, 784111777);
+    }
+
+    #[test]
+    fn unrecognised_string_is_an_error() {
+        let result = serde_json::from_str::<De<Tm>>(r#""not a date""#);
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(test)]
+mod base64_header_value_tests {
+    extern crate serde_json;
+
+    use super::{De, Ser};
+    use hyper::header::Headers;
+
+    #[test]
+    fn non_utf8_value_round_trips_through_base64() {
+        let mut headers = Headers::new();
+        headers.set_raw("X-Binary", vec![vec![0xff, 0x00, 0x80]]);
+
+        let json = serde_json::to_string(&Ser::new_base64(&headers)).unwrap();
+        assert!(json.contains("b64"));
+        assert!(!json.contains("255"));
+
+        let de: De<Headers> = serde_json::from_str(&json).unwrap();
+        let roundtripped = de.into_inner();
+        assert_eq!(roundtripped.get_raw("X-Binary").unwrap().one().unwrap(),
+                   &[0xff, 0x00, 0x80][..]);
+    }
+
+    #[test]
+    fn utf8_value_stays_a_string_in_base64_mode() {
+        let mut headers = Headers::new();
+        headers.set_raw("X-Text", vec![b"hello".to_vec()]);
+
+        let json = serde_json::to_string(&Ser::new_base64(&headers)).unwrap();
+        assert!(json.contains("\"hello\""));
+
+        let de: De<Headers> = serde_json::from_str(&json).unwrap();
+        let roundtripped = de.into_inner();
+        assert_eq!(roundtripped.get_raw("X-Text").unwrap().one().unwrap(), b"hello");
+    }
+}
+
+#[cfg(test)]
+mod duplicate_header_name_tests {
+    extern crate serde_json;
+
+    use super::{De, DeHeaders, FirstHeaderNameWins, LastHeaderNameWins,
+                RejectDuplicateHeaderName};
+
+    const DUPLICATE_SET_COOKIE: &str =
+        r#"{"Set-Cookie":["a"],"Set-Cookie":["b"]}"#;
+
+    fn values(headers: &::hyper::header::Headers) -> Vec<&[u8]> {
+        headers.get_raw("Set-Cookie").unwrap().iter().collect()
+    }
+
+    #[test]
+    fn append_is_the_default_for_de_headers() {
+        let de: De<::hyper::header::Headers> =
+            serde_json::from_str(DUPLICATE_SET_COOKIE).unwrap();
+        let headers = de.into_inner();
+        assert_eq!(values(&headers), vec![b"a".as_ref(), b"b".as_ref()]);
+    }
+
+    #[test]
+    fn first_header_name_wins() {
+        let de: DeHeaders<FirstHeaderNameWins> =
+            serde_json::from_str(DUPLICATE_SET_COOKIE).unwrap();
+        let headers = de.into_inner();
+        assert_eq!(values(&headers), vec![b"a".as_ref()]);
+    }
+
+    #[test]
+    fn last_header_name_wins() {
+        let de: DeHeaders<LastHeaderNameWins> =
+            serde_json::from_str(DUPLICATE_SET_COOKIE).unwrap();
+        let headers = de.into_inner();
+        assert_eq!(values(&headers), vec![b"b".as_ref()]);
+    }
+
+    #[test]
+    fn reject_duplicate_header_name_errors() {
+        let result: Result<DeHeaders<RejectDuplicateHeaderName>, _> =
+            serde_json::from_str(DUPLICATE_SET_COOKIE);
+        assert!(result.is_err());
+    }
+}